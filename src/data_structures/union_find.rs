@@ -9,20 +9,71 @@
 //! - [Wikipedia](https://www.wikiwand.com/en/Prim%27s_algorithm)
 
 use std::cmp::Ordering::*;
+use std::collections::HashMap;
+use std::hash::Hash;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+/// An unsigned integer type usable as a compact index into `UnionFind`'s internal vectors.
+///
+/// Letting callers pick `u32` (or similar) instead of `usize` roughly halves the memory
+/// footprint of the parent array for million-node workloads, which improves cache locality
+/// during `find`.
+pub trait IndexType: Copy {
+    fn index(self) -> usize;
+    fn from_usize(value: usize) -> Self;
+}
+
+impl IndexType for usize {
+    fn index(self) -> usize {
+        self
+    }
+
+    fn from_usize(value: usize) -> Self {
+        value
+    }
+}
+
+impl IndexType for u32 {
+    fn index(self) -> usize {
+        self as usize
+    }
+
+    fn from_usize(value: usize) -> Self {
+        value as u32
+    }
+}
 
 /// Vector-based union-find representing a set of disjoint sets.
+///
+/// `K` is the integer type used to store parent indices internally; it defaults to `usize`
+/// but can be set to a narrower type such as `u32` to shrink the parent array for large
+/// graphs. Ranks are at most logarithmic in the number of elements, so they are always
+/// packed into a `u8` regardless of `K`.
 #[derive(Clone)]
-pub struct UnionFind {
-    parents: Vec<usize>,
-    ranks: Vec<usize>,
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct UnionFind<K: IndexType = usize> {
+    parents: Vec<K>,
+    ranks: Vec<u8>,
+    sizes: Vec<usize>,
+    num_sets: usize,
+    // Reusable scratch buffer for `compress_path`, avoiding a fresh allocation per call;
+    // not worth persisting, so it's rebuilt empty on deserialization.
+    #[cfg_attr(feature = "serde", serde(skip))]
+    find_parent_list: Vec<usize>,
 }
 
-impl UnionFind {
+impl<K: IndexType> UnionFind<K> {
     pub fn with_size(size: usize) -> Self {
         UnionFind {
             // parents are initialised to invalid values
-            parents: (0..size).collect(),
+            parents: (0..size).map(K::from_usize).collect(),
             ranks: vec![0; size],
+            sizes: vec![1; size],
+            num_sets: size,
+            find_parent_list: Vec::new(),
         }
     }
 
@@ -30,8 +81,11 @@ impl UnionFind {
         let size = ranks.len();
         UnionFind {
             // parents are initialised to invalid values
-            parents: (0..size).collect(),
-            ranks,
+            parents: (0..size).map(K::from_usize).collect(),
+            ranks: ranks.into_iter().map(|r| r.min(u8::MAX as usize) as u8).collect(),
+            sizes: vec![1; size],
+            num_sets: size,
+            find_parent_list: Vec::new(),
         }
     }
 
@@ -46,9 +100,11 @@ impl UnionFind {
     pub fn extend(&mut self, size: usize) {
         let n = self.len();
         for i in n..n + size {
-            self.parents.push(i);
+            self.parents.push(K::from_usize(i));
             self.ranks.push(0);
+            self.sizes.push(1);
         }
+        self.num_sets += size;
     }
 
     /// Try to union two sets.
@@ -63,14 +119,23 @@ impl UnionFind {
         let rank_a = self.ranks[rep_a];
         let rank_b = self.ranks[rep_b];
 
-        match rank_a.cmp(&rank_b) {
-            Greater => self.set_parent(rep_b, rep_a),
-            Less => self.set_parent(rep_a, rep_b),
+        let (surviving_root, absorbed_root) = match rank_a.cmp(&rank_b) {
+            Greater => {
+                self.set_parent(rep_b, rep_a);
+                (rep_a, rep_b)
+            }
+            Less => {
+                self.set_parent(rep_a, rep_b);
+                (rep_b, rep_a)
+            }
             Equal => {
                 self.set_parent(rep_a, rep_b);
                 self.increment_rank(rep_b);
+                (rep_b, rep_a)
             }
-        }
+        };
+        self.sizes[surviving_root] += self.sizes[absorbed_root];
+        self.num_sets -= 1;
 
         true
     }
@@ -92,16 +157,351 @@ impl UnionFind {
         self.find(a) == self.find(b)
     }
 
+    /// Finds the representative element for the given element’s set without compressing
+    /// paths, so it can be called behind a shared reference (e.g. from multiple readers).
+    pub fn find_immutable(&self, mut element: usize) -> usize {
+        let mut parent = self.parent(element);
+        while element != parent {
+            element = parent;
+            parent = self.parent(parent);
+        }
+
+        element
+    }
+
+    pub fn in_same_set_immutable(&self, a: usize, b: usize) -> bool {
+        self.find_immutable(a) == self.find_immutable(b)
+    }
+
+    /// Walks `element` to its root like [`find_immutable`](Self::find_immutable), then
+    /// compresses the path, reusing an internal scratch buffer instead of allocating one
+    /// per call. Useful for applying compression in bulk after a batch of immutable queries.
+    pub fn compress_path(&mut self, element: usize) -> usize {
+        self.find_parent_list.clear();
+        let mut node = element;
+        let mut parent = self.parent(node);
+        while node != parent {
+            self.find_parent_list.push(node);
+            node = parent;
+            parent = self.parent(parent);
+        }
+
+        let root = node;
+        for i in 0..self.find_parent_list.len() {
+            let element = self.find_parent_list[i];
+            self.set_parent(element, root);
+        }
+
+        root
+    }
+
+    /// Returns the number of elements in `element`'s set.
+    pub fn set_size(&mut self, element: usize) -> usize {
+        let root = self.find(element);
+        self.sizes[root]
+    }
+
+    /// Returns the number of disjoint sets currently tracked.
+    pub fn count_sets(&self) -> usize {
+        self.num_sets
+    }
+
     fn increment_rank(&mut self, element: usize) {
         self.ranks[element] = self.ranks[element].saturating_add(1);
     }
 
     pub fn parent(&self, element: usize) -> usize {
-        self.parents[element]
+        self.parents[element].index()
     }
 
     pub fn set_parent(&mut self, element: usize, parent: usize) {
-        self.parents[element] = parent;
+        self.parents[element] = K::from_usize(parent);
+    }
+}
+
+/// Drives how two set payloads combine when [`PayloadUnionFind::union`] merges their roots.
+///
+/// The returned `bool` says whether `right` becomes the new root (`false` keeps `left` as
+/// root); the returned `Self` is the combined payload stored on whichever side wins.
+pub trait UnionNode: Sized {
+    fn merge(left: &Self, right: &Self) -> (bool, Self);
+}
+
+/// Tracks the size of each set, reproducing the size-counting behaviour of [`UnionFind`]
+/// as a [`UnionNode`] payload. Useful as a starting point for custom payloads.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct SizeNode {
+    pub size: usize,
+}
+
+impl SizeNode {
+    pub fn new() -> Self {
+        SizeNode { size: 1 }
+    }
+}
+
+impl Default for SizeNode {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl UnionNode for SizeNode {
+    fn merge(left: &Self, right: &Self) -> (bool, Self) {
+        let merged = SizeNode {
+            size: left.size + right.size,
+        };
+        // Keep the larger set's root (union by size); on a tie `left` stays the root.
+        (right.size > left.size, merged)
+    }
+}
+
+/// Union-find where each root carries a user-defined payload `N`, merged via [`UnionNode`]
+/// instead of rank, so callers can accumulate arbitrary per-component state (total weight,
+/// min/max label, bounding boxes, ...) without maintaining a parallel side table.
+#[derive(Clone)]
+pub struct PayloadUnionFind<N: UnionNode> {
+    parents: Vec<usize>,
+    nodes: Vec<N>,
+}
+
+impl<N: UnionNode> PayloadUnionFind<N> {
+    /// Builds a union-find with one singleton set per entry in `payloads`.
+    pub fn new(payloads: Vec<N>) -> Self {
+        let size = payloads.len();
+        PayloadUnionFind {
+            parents: (0..size).collect(),
+            nodes: payloads,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.parents.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.parents.is_empty()
+    }
+
+    /// Finds the representative element for the given element’s set.
+    pub fn find(&mut self, mut element: usize) -> usize {
+        let mut parent = self.parents[element];
+        while element != parent {
+            let next_parent = self.parents[parent];
+            self.parents[element] = next_parent;
+            element = parent;
+            parent = next_parent;
+        }
+
+        element
+    }
+
+    /// Try to union two sets, merging their payloads via [`UnionNode::merge`].
+    pub fn union(&mut self, a: usize, b: usize) -> bool {
+        let rep_a = self.find(a);
+        let rep_b = self.find(b);
+
+        if rep_a == rep_b {
+            return false;
+        }
+
+        let (right_becomes_root, merged) = N::merge(&self.nodes[rep_a], &self.nodes[rep_b]);
+        if right_becomes_root {
+            self.parents[rep_a] = rep_b;
+            self.nodes[rep_b] = merged;
+        } else {
+            self.parents[rep_b] = rep_a;
+            self.nodes[rep_a] = merged;
+        }
+
+        true
+    }
+
+    pub fn in_same_set(&mut self, a: usize, b: usize) -> bool {
+        self.find(a) == self.find(b)
+    }
+
+    /// Returns the payload carried by `element`'s set root.
+    pub fn payload(&mut self, element: usize) -> &N {
+        let root = self.find(element);
+        &self.nodes[root]
+    }
+}
+
+/// Union-find over arbitrary hashable values, for callers who don't want to
+/// hand-roll a value-to-index mapping before delegating to [`UnionFind`].
+#[derive(Clone)]
+pub struct LabeledUnionFind<T: Eq + Hash> {
+    entry_index: HashMap<T, usize>,
+    inner: UnionFind,
+}
+
+impl<T: Eq + Hash> LabeledUnionFind<T> {
+    pub fn new() -> Self {
+        LabeledUnionFind {
+            entry_index: HashMap::new(),
+            inner: UnionFind::with_size(0),
+        }
+    }
+
+    /// Inserts `value` if it hasn't been seen before, returning its index either way.
+    pub fn add(&mut self, value: T) -> usize {
+        if let Some(&index) = self.entry_index.get(&value) {
+            return index;
+        }
+
+        let index = self.inner.len();
+        self.inner.extend(1);
+        self.entry_index.insert(value, index);
+        index
+    }
+
+    pub fn len(&self) -> usize {
+        self.inner.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.inner.is_empty()
+    }
+
+    /// Unions the sets containing `a` and `b`, adding either value first if unseen.
+    pub fn union(&mut self, a: T, b: T) -> bool {
+        let a = self.add(a);
+        let b = self.add(b);
+        self.inner.union(a, b)
+    }
+
+    /// Returns the representative index for `value`'s set, adding it first if unseen.
+    pub fn find(&mut self, value: T) -> usize {
+        let index = self.add(value);
+        self.inner.find(index)
+    }
+
+    pub fn in_same_set(&mut self, a: T, b: T) -> bool {
+        self.find(a) == self.find(b)
+    }
+
+    /// Returns the number of elements in `value`'s set, adding it first if unseen.
+    pub fn set_size(&mut self, value: T) -> usize {
+        let index = self.add(value);
+        self.inner.set_size(index)
+    }
+
+    /// Returns the number of disjoint sets currently tracked.
+    pub fn count_sets(&self) -> usize {
+        self.inner.count_sets()
+    }
+}
+
+impl<T: Eq + Hash> Default for LabeledUnionFind<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Lock-free union-find that can be shared across threads behind a plain reference,
+/// letting `union`/`find` run concurrently without a global lock. Useful for computing
+/// connected components over large edge lists in parallel, which the single-threaded
+/// `&mut self` [`UnionFind`] API can't express.
+pub struct AUnionFind {
+    elements: Box<[AtomicUsize]>,
+    ranks: Box<[AtomicUsize]>,
+}
+
+impl AUnionFind {
+    pub fn with_size(size: usize) -> Self {
+        AUnionFind {
+            elements: (0..size).map(AtomicUsize::new).collect(),
+            ranks: (0..size).map(|_| AtomicUsize::new(0)).collect(),
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.elements.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.elements.is_empty()
+    }
+
+    /// Finds the representative element for the given element’s set, applying path-halving
+    /// via `compare_exchange` as it walks (a losing CAS just retries from the observed parent).
+    pub fn find(&self, mut element: usize) -> usize {
+        loop {
+            let parent = self.elements[element].load(Ordering::Relaxed);
+            if parent == element {
+                return element;
+            }
+
+            let grandparent = self.elements[parent].load(Ordering::Relaxed);
+            if grandparent == parent {
+                return parent;
+            }
+
+            element = match self.elements[element].compare_exchange(
+                parent,
+                grandparent,
+                Ordering::Relaxed,
+                Ordering::Relaxed,
+            ) {
+                Ok(_) => grandparent,
+                Err(observed) => observed,
+            };
+        }
+    }
+
+    /// Try to union two sets. Safe to call concurrently: if another thread moves a root
+    /// between locating it and linking it, the losing side simply retries.
+    pub fn union(&self, a: usize, b: usize) -> bool {
+        loop {
+            let rep_a = self.find(a);
+            let rep_b = self.find(b);
+
+            if rep_a == rep_b {
+                return false;
+            }
+
+            let rank_a = self.ranks[rep_a].load(Ordering::Relaxed);
+            let rank_b = self.ranks[rep_b].load(Ordering::Relaxed);
+
+            let (child, new_root) = match rank_a.cmp(&rank_b) {
+                Greater => (rep_b, rep_a),
+                Less => (rep_a, rep_b),
+                Equal => (rep_a, rep_b),
+            };
+
+            if self.elements[child]
+                .compare_exchange(child, new_root, Ordering::Relaxed, Ordering::Relaxed)
+                .is_ok()
+            {
+                if rank_a == rank_b {
+                    self.ranks[new_root].fetch_add(1, Ordering::Relaxed);
+                }
+                return true;
+            }
+            // Another thread linked one of the roots elsewhere in the meantime; retry.
+        }
+    }
+
+    pub fn in_same_set(&self, a: usize, b: usize) -> bool {
+        self.find(a) == self.find(b)
+    }
+}
+
+impl Clone for AUnionFind {
+    fn clone(&self) -> Self {
+        AUnionFind {
+            elements: self
+                .elements
+                .iter()
+                .map(|e| AtomicUsize::new(e.load(Ordering::Relaxed)))
+                .collect(),
+            ranks: self
+                .ranks
+                .iter()
+                .map(|r| AtomicUsize::new(r.load(Ordering::Relaxed)))
+                .collect(),
+        }
     }
 }
 
@@ -111,7 +511,7 @@ mod tests {
 
     #[test]
     fn test_union_find() {
-        let mut uf = UnionFind::with_size(7);
+        let mut uf: UnionFind = UnionFind::with_size(7);
         uf.extend(1);
         assert_eq!(uf.len(), 8);
         assert!(!uf.is_empty());
@@ -137,4 +537,156 @@ mod tests {
         uf.union(0, 7);
         assert!(uf.in_same_set(5, 7));
     }
+
+    #[test]
+    fn test_union_find_sizes() {
+        let mut uf: UnionFind = UnionFind::with_size(5);
+        assert_eq!(uf.count_sets(), 5);
+        assert_eq!(uf.set_size(0), 1);
+
+        uf.union(0, 1);
+        assert_eq!(uf.count_sets(), 4);
+        assert_eq!(uf.set_size(0), 2);
+        assert_eq!(uf.set_size(1), 2);
+
+        uf.union(2, 3);
+        uf.union(1, 2);
+        assert_eq!(uf.count_sets(), 2);
+        assert_eq!(uf.set_size(3), 4);
+
+        assert!(!uf.union(0, 3));
+        assert_eq!(uf.count_sets(), 2);
+
+        uf.extend(2);
+        assert_eq!(uf.count_sets(), 4);
+        assert_eq!(uf.set_size(5), 1);
+    }
+
+    #[test]
+    fn test_union_find_u32_index() {
+        let mut uf: UnionFind<u32> = UnionFind::with_size(6);
+        assert!(uf.union(0, 1));
+        assert!(uf.union(1, 2));
+        assert!(!uf.union(0, 2));
+
+        assert!(uf.in_same_set(0, 2));
+        assert!(!uf.in_same_set(0, 3));
+        assert_eq!(uf.set_size(0), 3);
+        assert_eq!(uf.count_sets(), 4);
+    }
+
+    #[test]
+    fn test_union_find_immutable_queries() {
+        let mut uf: UnionFind = UnionFind::with_size(5);
+        uf.union(0, 1);
+        uf.union(1, 2);
+
+        assert!(uf.in_same_set_immutable(0, 2));
+        assert!(!uf.in_same_set_immutable(0, 3));
+        assert_eq!(uf.find_immutable(2), uf.find(2));
+
+        let root = uf.compress_path(0);
+        assert_eq!(uf.parent(0), root);
+        assert_eq!(root, uf.find_immutable(2));
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_union_find_serde_roundtrip() {
+        let mut uf: UnionFind = UnionFind::with_size(5);
+        uf.union(0, 1);
+        uf.union(1, 2);
+        uf.extend(2);
+        uf.union(5, 6);
+
+        let json = serde_json::to_string(&uf).unwrap();
+        let mut restored: UnionFind = serde_json::from_str(&json).unwrap();
+
+        assert_eq!(restored.len(), uf.len());
+        assert!(restored.in_same_set(0, 2));
+        assert!(restored.in_same_set(5, 6));
+        assert!(!restored.in_same_set(0, 5));
+    }
+
+    #[test]
+    fn test_payload_union_find() {
+        let mut uf = PayloadUnionFind::new(vec![SizeNode::new(); 5]);
+
+        assert!(uf.union(0, 1));
+        assert_eq!(uf.payload(0).size, 2);
+
+        assert!(uf.union(2, 3));
+        assert!(uf.union(0, 2));
+        assert_eq!(uf.payload(3).size, 4);
+        assert!(uf.in_same_set(1, 3));
+
+        assert!(!uf.union(0, 1));
+        assert!(!uf.in_same_set(0, 4));
+    }
+
+    #[test]
+    fn test_labeled_union_find() {
+        let mut uf = LabeledUnionFind::new();
+        assert!(uf.is_empty());
+
+        assert!(uf.union('a', 'b'));
+        assert!(uf.union('b', 'c'));
+        assert!(!uf.union('a', 'c'));
+        assert_eq!(uf.len(), 3);
+
+        assert!(uf.in_same_set('a', 'c'));
+        assert!(!uf.in_same_set('a', 'd'));
+
+        uf.add('d');
+        assert_eq!(uf.len(), 4);
+        assert!(!uf.in_same_set('a', 'd'));
+
+        uf.union('d', 'a');
+        assert!(uf.in_same_set('b', 'd'));
+    }
+
+    #[test]
+    fn test_atomic_union_find() {
+        let uf = AUnionFind::with_size(8);
+        assert!(uf.union(0, 1));
+        assert!(uf.union(1, 2));
+        assert!(!uf.union(0, 2));
+
+        assert!(uf.in_same_set(0, 2));
+        assert!(!uf.in_same_set(0, 3));
+
+        uf.union(6, 7);
+        assert!(uf.in_same_set(6, 7));
+        assert!(!uf.in_same_set(5, 7));
+    }
+
+    #[test]
+    fn test_atomic_union_find_concurrent() {
+        use std::sync::Arc;
+        use std::thread;
+
+        let uf = Arc::new(AUnionFind::with_size(100));
+        let mut handles = Vec::new();
+
+        for t in 0..4 {
+            let uf = Arc::clone(&uf);
+            handles.push(thread::spawn(move || {
+                for i in 0..25 {
+                    let element = t * 25 + i;
+                    uf.union(t * 25, element);
+                }
+            }));
+        }
+
+        for handle in handles {
+            handle.join().unwrap();
+        }
+
+        for t in 0..4 {
+            for i in 0..25 {
+                assert!(uf.in_same_set(t * 25, t * 25 + i));
+            }
+        }
+        assert!(!uf.in_same_set(0, 25));
+    }
 }